@@ -2,6 +2,7 @@
 //! Ultra-Functioning: embed() -> Id, query() -> Vec<SearchMatch>
 
 use std::ffi::{CStr, CString, c_char};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, OnceLock};
 use std::ptr;
 use tokio::runtime::Runtime;
@@ -9,12 +10,27 @@ use serde::{Deserialize, Serialize};
 use surrealdb::{Surreal, engine::local::Db, engine::local::RocksDb, sql::Id, sql::Thing};
 use embed_anything::{embeddings::embed::Embedder, embed_query};
 use anyhow::Result;
+use usearch::{Index, IndexOptions, MetricKind, ScalarKind};
+use tokenizers::Tokenizer;
 
 // Global state - initialized once via withConfig()
 static RUNTIME: OnceLock<Runtime> = OnceLock::new();
 static mut DATABASE: Option<Arc<Surreal<Db>>> = None;
 static mut EMBEDDER: Option<Arc<Embedder>> = None;
 static mut CONFIG: Option<GlobalConfig> = None;
+// Approximate-nearest-neighbor index over the same vectors stored in
+// SurrealDB. Keyed by `vkey`, a monotonically increasing u64 persisted
+// alongside each row, since usearch needs an integer key, not a Thing.
+static USEARCH_INDEX: OnceLock<Index> = OnceLock::new();
+static VKEY_COUNTER: AtomicU64 = AtomicU64::new(0);
+// embed_cached hit/miss counters, surfaced via cache_stats()
+static CACHE_HITS: AtomicU64 = AtomicU64::new(0);
+static CACHE_MISSES: AtomicU64 = AtomicU64::new(0);
+// Model tokenizer, used to truncate/window oversized inputs before embed_query
+static TOKENIZER: OnceLock<Tokenizer> = OnceLock::new();
+
+// Fallback context window when EmbedAnythingConfig.max_tokens is unset.
+const DEFAULT_MAX_TOKENS: usize = 512;
 
 // Configuration types - matching EmbedAnything's actual capabilities
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -24,6 +40,21 @@ pub struct GlobalConfig {
     pub embedding: EmbedAnythingConfig,
     pub threshold: f32,
     pub limit: u32,
+    pub hybrid: Option<HybridStrategy>,
+    /// When true, query() includes each match's stored metadata object
+    /// (file_path, symbol_name, signature, line, summary, ...) in
+    /// SearchMatch. Defaults to false, preserving the old Id-only results.
+    #[serde(default)]
+    pub include_metadata: bool,
+}
+
+// Hybrid keyword+vector search config - fuses BM25 full-text matches with
+// vector similarity via Reciprocal Rank Fusion (see query_hybrid).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HybridStrategy {
+    /// Weight given to the vector ranking in the RRF fusion, 0.0-1.0.
+    /// The keyword ranking gets the remaining `1.0 - semantic_ratio`.
+    pub semantic_ratio: f32,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -31,7 +62,11 @@ pub struct EmbedAnythingConfig {
     pub model_architecture: String,    // "jina", "bert", "clip"
     pub model_id: String,              // "jinaai/jina-embeddings-v2-small-en"
     pub revision: Option<String>,      // "main" or specific commit
-    pub batch_size: Option<usize>,     // Processing batch size
+    pub batch_size: Option<usize>,     // Processing batch size; also the max items per embed_batch flush
+    pub vector_dims: usize,            // Output dimensionality, for the usearch index
+    pub token_budget: Option<usize>,   // embed_batch flushes once accumulated tokens cross this
+    pub max_tokens: Option<usize>,     // Truncation window for embed_text, defaults to DEFAULT_MAX_TOKENS
+    pub window_overlap: Option<usize>, // Token overlap between windows when text exceeds max_tokens
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -46,6 +81,153 @@ pub enum RankingStrategy {
 pub struct SearchMatch {
     pub id: String,                    // Serialized Id for FFI transfer
     pub similarity_score: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+// RRF rank constant - standard choice, dampens the influence of any single
+// list's top rank so neither vector nor keyword results dominate the fusion.
+const RRF_K: f32 = 60.0;
+
+// Keyword search template - BM25 full-text match over the stored `text` field.
+fn get_keyword_query_template() -> &'static str {
+    r#"
+        SELECT id, search::score(0) AS relevance
+        FROM embeddings
+        WHERE text @@ $query_text
+        ORDER BY relevance DESC
+        LIMIT $k
+    "#
+}
+
+// Fuse a vector-ranked and a keyword-ranked result list via Reciprocal Rank
+// Fusion: score = semantic_ratio/(k+rank_vec) + (1-semantic_ratio)/(k+rank_kw),
+// rank is 0-based. Documents appearing in only one list still score via that
+// list alone. Returns ids sorted by fused score, descending.
+fn rrf_fuse(
+    vector_ranked: &[Id],
+    keyword_ranked: &[Id],
+    semantic_ratio: f32,
+    limit: usize,
+) -> Vec<(Id, f32)> {
+    use std::collections::HashMap;
+
+    let mut scores: HashMap<String, (Id, f32)> = HashMap::new();
+
+    for (rank, id) in vector_ranked.iter().enumerate() {
+        let contribution = semantic_ratio / (RRF_K + rank as f32);
+        let entry = scores.entry(id.to_string()).or_insert_with(|| (id.clone(), 0.0));
+        entry.1 += contribution;
+    }
+
+    for (rank, id) in keyword_ranked.iter().enumerate() {
+        let contribution = (1.0 - semantic_ratio) / (RRF_K + rank as f32);
+        let entry = scores.entry(id.to_string()).or_insert_with(|| (id.clone(), 0.0));
+        entry.1 += contribution;
+    }
+
+    let mut fused: Vec<(Id, f32)> = scores.into_values().collect();
+    fused.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    fused.truncate(limit);
+    fused
+}
+
+#[cfg(test)]
+mod rrf_fuse_tests {
+    use super::*;
+
+    fn ids(values: &[&str]) -> Vec<Id> {
+        values.iter().map(|v| Id::from(v.to_string())).collect()
+    }
+
+    fn id_strings(fused: &[(Id, f32)]) -> Vec<String> {
+        fused.iter().map(|(id, _)| id.to_string()).collect()
+    }
+
+    #[test]
+    fn disjoint_lists_keep_both_sides_scores() {
+        let vector_ranked = ids(&["a", "b"]);
+        let keyword_ranked = ids(&["c", "d"]);
+
+        let fused = rrf_fuse(&vector_ranked, &keyword_ranked, 0.5, 10);
+
+        assert_eq!(fused.len(), 4);
+        let a_score = 0.5 / (RRF_K + 0.0);
+        let b_score = 0.5 / (RRF_K + 1.0);
+        assert!((fused.iter().find(|(id, _)| id.to_string() == "a").unwrap().1 - a_score).abs() < 1e-6);
+        assert!((fused.iter().find(|(id, _)| id.to_string() == "b").unwrap().1 - b_score).abs() < 1e-6);
+    }
+
+    #[test]
+    fn overlapping_ids_sum_contributions_from_both_lists() {
+        let vector_ranked = ids(&["a", "b"]);
+        let keyword_ranked = ids(&["b", "a"]);
+
+        let fused = rrf_fuse(&vector_ranked, &keyword_ranked, 0.5, 10);
+
+        let a = fused.iter().find(|(id, _)| id.to_string() == "a").unwrap().1;
+        let b = fused.iter().find(|(id, _)| id.to_string() == "b").unwrap().1;
+        let expected_a = 0.5 / (RRF_K + 0.0) + 0.5 / (RRF_K + 1.0);
+        let expected_b = 0.5 / (RRF_K + 1.0) + 0.5 / (RRF_K + 0.0);
+        assert!((a - expected_a).abs() < 1e-6);
+        assert!((b - expected_b).abs() < 1e-6);
+    }
+
+    #[test]
+    fn semantic_ratio_zero_ignores_vector_list() {
+        let vector_ranked = ids(&["a"]);
+        let keyword_ranked = ids(&["b"]);
+
+        let fused = rrf_fuse(&vector_ranked, &keyword_ranked, 0.0, 10);
+
+        assert_eq!(fused.iter().find(|(id, _)| id.to_string() == "a").unwrap().1, 0.0);
+        assert!(fused.iter().find(|(id, _)| id.to_string() == "b").unwrap().1 > 0.0);
+    }
+
+    #[test]
+    fn semantic_ratio_one_ignores_keyword_list() {
+        let vector_ranked = ids(&["a"]);
+        let keyword_ranked = ids(&["b"]);
+
+        let fused = rrf_fuse(&vector_ranked, &keyword_ranked, 1.0, 10);
+
+        assert!(fused.iter().find(|(id, _)| id.to_string() == "a").unwrap().1 > 0.0);
+        assert_eq!(fused.iter().find(|(id, _)| id.to_string() == "b").unwrap().1, 0.0);
+    }
+
+    #[test]
+    fn semantic_ratio_half_weights_both_lists_equally() {
+        let vector_ranked = ids(&["a"]);
+        let keyword_ranked = ids(&["a"]);
+
+        let fused = rrf_fuse(&vector_ranked, &keyword_ranked, 0.5, 10);
+
+        let expected = 0.5 / (RRF_K + 0.0) + 0.5 / (RRF_K + 0.0);
+        assert!((fused[0].1 - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rank_is_zero_based_and_uses_rrf_k() {
+        let vector_ranked = ids(&["a", "b", "c"]);
+        let keyword_ranked: Vec<Id> = Vec::new();
+
+        let fused = rrf_fuse(&vector_ranked, &keyword_ranked, 1.0, 10);
+
+        assert!((fused[0].1 - 1.0 / RRF_K).abs() < 1e-6);
+        assert!((fused[1].1 - 1.0 / (RRF_K + 1.0)).abs() < 1e-6);
+        assert!((fused[2].1 - 1.0 / (RRF_K + 2.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn results_are_sorted_descending_and_truncated_to_limit() {
+        let vector_ranked = ids(&["a", "b", "c"]);
+        let keyword_ranked: Vec<Id> = Vec::new();
+
+        let fused = rrf_fuse(&vector_ranked, &keyword_ranked, 1.0, 2);
+
+        assert_eq!(fused.len(), 2);
+        assert_eq!(id_strings(&fused), vec!["a".to_string(), "b".to_string()]);
+    }
 }
 
 // SurrealQL query templates - PERFECTED syntax
@@ -86,6 +268,212 @@ fn get_query_template(strategy: &RankingStrategy) -> &'static str {
     }
 }
 
+// ANN candidate search: queries the in-memory HNSW index for the
+// `limit * 10` nearest vkeys, fetches those rows from SurrealDB, and
+// rescores them exactly with the configured RankingStrategy. This avoids
+// the full-table scan in get_query_template once the index is populated.
+async fn query_vector_ann(
+    db: &Surreal<Db>,
+    config: &GlobalConfig,
+    query_vector: &[f32],
+) -> Result<Vec<(Id, f32)>> {
+    let index = USEARCH_INDEX.get().ok_or_else(|| anyhow::anyhow!("ANN index not initialized"))?;
+    let candidate_count = (config.limit as usize).saturating_mul(10).max(1);
+    let results = index.search(query_vector, candidate_count)?;
+
+    if results.keys.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let vkeys: Vec<i64> = results.keys.iter().map(|k| *k as i64).collect();
+
+    #[derive(Serialize, Deserialize, Debug)]
+    struct CandidateRow {
+        id: Thing,
+        vector: Vec<f32>,
+    }
+
+    let mut response = db.query("SELECT id, vector FROM embeddings WHERE vkey IN $vkeys")
+        .bind(("vkeys", vkeys))
+        .await?;
+    let rows: Vec<CandidateRow> = response.take(0)?;
+
+    let mut scored: Vec<(Id, f32)> = rows
+        .into_iter()
+        .map(|row| {
+            let score = similarity(&config.ranking, query_vector, &row.vector);
+            (row.id.id, score)
+        })
+        .filter(|(_, score)| *score > config.threshold)
+        .collect();
+
+    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    scored.truncate(config.limit as usize);
+    Ok(scored)
+}
+
+// Hybrid keyword+vector query path - runs the configured vector ranking
+// alongside a BM25 keyword search over `text`, then fuses both rankings
+// with RRF weighted by `semantic_ratio`. Candidate lists are fetched at
+// `limit` each so the fused result still respects the configured limit.
+async fn query_hybrid(
+    db: &Surreal<Db>,
+    config: &GlobalConfig,
+    hybrid: &HybridStrategy,
+    query_vector: Vec<f32>,
+    query_text: &str,
+) -> Result<Vec<SearchMatch>> {
+    #[derive(Serialize, Deserialize, Debug)]
+    struct VectorResult {
+        id: Thing,
+        similarity_score: f64,
+    }
+
+    #[derive(Serialize, Deserialize, Debug)]
+    struct KeywordResult {
+        id: Thing,
+        relevance: f64,
+    }
+
+    let vector_ids: Vec<Id> = match USEARCH_INDEX.get().filter(|idx| idx.size() > 0 && ann_supported(&config.ranking)) {
+        Some(_) => query_vector_ann(db, config, &query_vector).await?
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect(),
+        None => {
+            let vector_template = get_query_template(&config.ranking);
+            let mut vector_response = db.query(vector_template)
+                .bind(("query_vector", query_vector))
+                .bind(("threshold", config.threshold))
+                .bind(("limit", config.limit as i64))
+                .await?;
+            let vector_results: Vec<VectorResult> = vector_response.take(0)?;
+            vector_results.into_iter().map(|r| r.id.id).collect()
+        }
+    };
+
+    let mut keyword_response = db.query(get_keyword_query_template())
+        .bind(("query_text", query_text.to_string()))
+        .bind(("k", config.limit as i64))
+        .await?;
+    let keyword_results: Vec<KeywordResult> = keyword_response.take(0)?;
+
+    let keyword_ids: Vec<Id> = keyword_results.iter().map(|r| r.id.id.clone()).collect();
+
+    let fused = rrf_fuse(&vector_ids, &keyword_ids, hybrid.semantic_ratio, config.limit as usize);
+
+    to_search_matches(db, config, fused).await
+}
+
+// Batch-fetches the stored `metadata` object for a set of ids, used to
+// enrich SearchMatch when GlobalConfig.include_metadata is set. Kept as a
+// single query regardless of result-set size.
+async fn fetch_metadata_map(db: &Surreal<Db>, ids: &[Id]) -> Result<std::collections::HashMap<String, serde_json::Value>> {
+    if ids.is_empty() {
+        return Ok(std::collections::HashMap::new());
+    }
+
+    #[derive(Serialize, Deserialize, Debug)]
+    struct MetadataRow {
+        id: Thing,
+        metadata: Option<serde_json::Value>,
+    }
+
+    let things: Vec<Thing> = ids.iter().map(|id| Thing::from(("embeddings", id.clone()))).collect();
+
+    let mut response = db.query("SELECT id, metadata FROM embeddings WHERE id IN $ids")
+        .bind(("ids", things))
+        .await?;
+    let rows: Vec<MetadataRow> = response.take(0)?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| row.metadata.map(|m| (row.id.id.to_string(), m)))
+        .collect())
+}
+
+// Builds the final SearchMatch list from a scored (Id, similarity) list,
+// attaching stored metadata when GlobalConfig.include_metadata is set.
+async fn to_search_matches(db: &Surreal<Db>, config: &GlobalConfig, scored: Vec<(Id, f32)>) -> Result<Vec<SearchMatch>> {
+    let metadata_map = if config.include_metadata {
+        let ids: Vec<Id> = scored.iter().map(|(id, _)| id.clone()).collect();
+        fetch_metadata_map(db, &ids).await?
+    } else {
+        std::collections::HashMap::new()
+    };
+
+    Ok(scored
+        .into_iter()
+        .map(|(id, score)| {
+            let id_string = id.to_string();
+            let metadata = metadata_map.get(&id_string).cloned();
+            SearchMatch { id: id_string, similarity_score: score, metadata }
+        })
+        .collect())
+}
+
+// Map a RankingStrategy onto the closest usearch metric. usearch has no
+// Manhattan/L1 kernel, so this is only meaningful for strategies where
+// ann_supported() is true - see that function for why Manhattan can't
+// safely use an L2-based candidate pool.
+fn metric_for_strategy(strategy: &RankingStrategy) -> MetricKind {
+    match strategy {
+        RankingStrategy::Cosine => MetricKind::Cos,
+        RankingStrategy::Euclidean => MetricKind::L2sq,
+        RankingStrategy::Manhattan => MetricKind::L2sq,
+        RankingStrategy::Dot => MetricKind::IP,
+    }
+}
+
+// usearch has no Manhattan/L1 kernel, so the only way to ANN-search for it
+// would be squared-Euclidean candidates rescored by true Manhattan distance.
+// That changes recall, not just re-ranking: items genuinely nearest by L1
+// can fall outside an L2-selected candidate pool entirely, so a document
+// the brute-force path would return can silently vanish once the index is
+// populated. Known limitation - Manhattan always uses the exact brute-force
+// path in get_query_template until a real L1 ANN kernel is available.
+fn ann_supported(strategy: &RankingStrategy) -> bool {
+    !matches!(strategy, RankingStrategy::Manhattan)
+}
+
+// Build the in-memory HNSW index. Called once from with_config; repopulated
+// via rebuild_index() on cold start since usearch indexes don't persist
+// automatically.
+fn build_usearch_index(config: &GlobalConfig) -> Result<Index> {
+    let options = IndexOptions {
+        dimensions: config.embedding.vector_dims,
+        metric: metric_for_strategy(&config.ranking),
+        quantization: ScalarKind::F32,
+        ..Default::default()
+    };
+    let index = Index::new(&options)?;
+    index.reserve(1_000_000)?;
+    Ok(index)
+}
+
+// Exact similarity between a query vector and a candidate, using the same
+// formula as get_query_template's SurrealQL so ANN rescoring agrees with
+// the brute-force path.
+fn similarity(strategy: &RankingStrategy, query: &[f32], candidate: &[f32]) -> f32 {
+    match strategy {
+        RankingStrategy::Cosine => {
+            let dot: f32 = query.iter().zip(candidate).map(|(a, b)| a * b).sum();
+            let norm_q: f32 = query.iter().map(|v| v * v).sum::<f32>().sqrt();
+            let norm_c: f32 = candidate.iter().map(|v| v * v).sum::<f32>().sqrt();
+            dot / (norm_q * norm_c)
+        }
+        RankingStrategy::Euclidean => {
+            let dist: f32 = query.iter().zip(candidate).map(|(a, b)| (a - b).powi(2)).sum::<f32>().sqrt();
+            1.0 / (1.0 + dist)
+        }
+        RankingStrategy::Manhattan => {
+            let dist: f32 = query.iter().zip(candidate).map(|(a, b)| (a - b).abs()).sum();
+            1.0 / (1.0 + dist)
+        }
+        RankingStrategy::Dot => query.iter().zip(candidate).map(|(a, b)| a * b).sum(),
+    }
+}
+
 // Database connection with PERFECT SurrealQL
 async fn connect_database(db_path: &str) -> Result<Arc<Surreal<Db>>> {
     // Create .vibe directory if it doesn't exist
@@ -102,6 +490,17 @@ async fn connect_database(db_path: &str) -> Result<Arc<Surreal<Db>>> {
         DEFINE TABLE IF NOT EXISTS embeddings SCHEMAFULL;
         DEFINE FIELD IF NOT EXISTS id ON TABLE embeddings TYPE record<embeddings>;
         DEFINE FIELD IF NOT EXISTS vector ON TABLE embeddings TYPE array<float>;
+        DEFINE FIELD IF NOT EXISTS text ON TABLE embeddings TYPE string;
+        DEFINE FIELD IF NOT EXISTS vkey ON TABLE embeddings TYPE int;
+        DEFINE FIELD IF NOT EXISTS metadata ON TABLE embeddings FLEXIBLE TYPE option<object>;
+        DEFINE ANALYZER IF NOT EXISTS code_analyzer TOKENIZERS blank,class FILTERS lowercase,ascii;
+        DEFINE INDEX IF NOT EXISTS embeddings_text_search ON TABLE embeddings
+            COLUMNS text SEARCH ANALYZER code_analyzer BM25 HIGHLIGHTS;
+
+        DEFINE TABLE IF NOT EXISTS cache SCHEMAFULL;
+        DEFINE FIELD IF NOT EXISTS hash ON TABLE cache TYPE string;
+        DEFINE FIELD IF NOT EXISTS vector ON TABLE cache TYPE array<float>;
+        DEFINE INDEX IF NOT EXISTS cache_hash_idx ON TABLE cache COLUMNS hash UNIQUE;
     "#).await?;
     
     Ok(Arc::new(db))
@@ -119,23 +518,378 @@ fn create_embedder(config: &EmbedAnythingConfig) -> Result<Embedder> {
     Ok(embedder)
 }
 
+// Stride (in tokens) between consecutive window starts. `overlap` is
+// clamped below `max_tokens` first, so the stride is always at least 1 and
+// the window loop always advances. An overlap at or beyond the window size
+// gives a stride of 1, i.e. one window per token position on long inputs.
+fn window_stride(max_tokens: usize, overlap: usize) -> usize {
+    let overlap = overlap.min(max_tokens.saturating_sub(1));
+    max_tokens.saturating_sub(overlap).max(1)
+}
+
+// Splits `text` into overlapping windows of at most `max_tokens` tokens,
+// using the model's own tokenizer so truncation lines up with what the
+// model actually sees. Short inputs return a single, unmodified window.
+fn tokenize_windows(text: &str, max_tokens: usize, overlap: usize) -> Result<Vec<String>> {
+    let tokenizer = TOKENIZER.get().ok_or_else(|| anyhow::anyhow!("Tokenizer not initialized"))?;
+    let encoding = tokenizer.encode(text, false).map_err(|e| anyhow::anyhow!("tokenization failed: {e}"))?;
+    let ids = encoding.get_ids();
+
+    if ids.len() <= max_tokens {
+        return Ok(vec![text.to_string()]);
+    }
+
+    let stride = window_stride(max_tokens, overlap);
+    let mut windows = Vec::new();
+    let mut start = 0;
+    while start < ids.len() {
+        let end = (start + max_tokens).min(ids.len());
+        let decoded = tokenizer
+            .decode(&ids[start..end], true)
+            .map_err(|e| anyhow::anyhow!("detokenization failed: {e}"))?;
+        windows.push(decoded);
+        if end == ids.len() {
+            break;
+        }
+        start += stride;
+    }
+    Ok(windows)
+}
+
+// Element-wise mean across a set of equal-length vectors, used to collapse
+// a long document's per-window embeddings into a single vector.
+fn mean_pool(vectors: &[Vec<f32>]) -> Vec<f32> {
+    let dims = vectors[0].len();
+    let mut pooled = vec![0.0f32; dims];
+    for vector in vectors {
+        for (acc, value) in pooled.iter_mut().zip(vector) {
+            *acc += value;
+        }
+    }
+    for acc in pooled.iter_mut() {
+        *acc /= vectors.len() as f32;
+    }
+    pooled
+}
+
+#[cfg(test)]
+mod tokenize_windows_tests {
+    use super::*;
+
+    #[test]
+    fn stride_steps_by_the_unoverlapped_remainder() {
+        assert_eq!(window_stride(100, 20), 80);
+    }
+
+    #[test]
+    fn stride_is_clamped_to_at_least_one() {
+        assert_eq!(window_stride(100, 99), 1);
+    }
+
+    #[test]
+    fn overlap_at_or_beyond_max_tokens_is_clamped_below_it() {
+        // Without clamping, overlap >= max_tokens underflows stride to 0,
+        // which .max(1) hides but still advances one token at a time.
+        // Clamping overlap first keeps the degenerate case explicit and
+        // still bounded to a stride of at least 1.
+        assert_eq!(window_stride(10, 10), 1);
+        assert_eq!(window_stride(10, 50), 1);
+    }
+
+    #[test]
+    fn no_overlap_strides_by_the_full_window() {
+        assert_eq!(window_stride(100, 0), 100);
+    }
+}
+
+#[cfg(test)]
+mod mean_pool_tests {
+    use super::*;
+
+    #[test]
+    fn averages_each_dimension_independently() {
+        let vectors = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
+
+        let pooled = mean_pool(&vectors);
+
+        assert_eq!(pooled, vec![2.0, 3.0]);
+    }
+
+    #[test]
+    fn single_vector_is_returned_unchanged() {
+        let vectors = vec![vec![1.0, -2.0, 3.5]];
+
+        let pooled = mean_pool(&vectors);
+
+        assert_eq!(pooled, vec![1.0, -2.0, 3.5]);
+    }
+}
+
 // Private function: text -> vector (used by both embed and query)
 async fn embed_text(text: &str) -> Result<Vec<f32>> {
-    let embedder = unsafe { 
-        EMBEDDER.as_ref().ok_or_else(|| anyhow::anyhow!("Embedder not initialized"))? 
+    let embedder = unsafe {
+        EMBEDDER.as_ref().ok_or_else(|| anyhow::anyhow!("Embedder not initialized"))?
     };
-    
+    let config = unsafe {
+        CONFIG.as_ref().ok_or_else(|| anyhow::anyhow!("Config not initialized"))?
+    };
+
+    let max_tokens = config.embedding.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+    let overlap = config.embedding.window_overlap.unwrap_or(0);
+    let windows = tokenize_windows(text, max_tokens, overlap)?;
+
     // Use correct embed_query signature: &[&str], &Embedder, Option<&TextEmbedConfig>
-    let text_slice = &[text];
-    let embeddings = embed_query(text_slice, embedder, None).await?;
-    
-    // Extract the dense vector from EmbedData
-    if let Some(embed_data) = embeddings.first() {
-        let dense_vector = embed_data.embedding.to_dense()?;
-        Ok(dense_vector)
+    let window_refs: Vec<&str> = windows.iter().map(|w| w.as_str()).collect();
+    let embeddings = embed_query(&window_refs, embedder, None).await?;
+
+    let vectors: Vec<Vec<f32>> = embeddings
+        .iter()
+        .map(|embed_data| embed_data.embedding.to_dense())
+        .collect::<Result<Vec<_>>>()?;
+
+    if vectors.is_empty() {
+        return Err(anyhow::anyhow!("No embeddings generated"));
+    }
+
+    Ok(if vectors.len() == 1 {
+        vectors.into_iter().next().unwrap()
     } else {
-        Err(anyhow::anyhow!("No embeddings generated"))
+        mean_pool(&vectors)
+    })
+}
+
+// Content-addressed cache in front of embed_text: normalizes the input,
+// hashes it with blake3, and checks the `cache` table before paying for a
+// model run. Re-indexing a mostly-unchanged corpus hits this cache on
+// every unchanged file.
+async fn embed_cached(text: &str) -> Result<Vec<f32>> {
+    let normalized = text.trim();
+    let hash = blake3::hash(normalized.as_bytes()).to_hex().to_string();
+
+    let db = unsafe {
+        DATABASE.as_ref().ok_or_else(|| anyhow::anyhow!("Database not initialized"))?
+    };
+
+    #[derive(Serialize, Deserialize, Debug)]
+    struct CacheRow {
+        vector: Vec<f32>,
+    }
+
+    let mut response = db.query("SELECT vector FROM cache WHERE hash = $hash LIMIT 1")
+        .bind(("hash", hash.clone()))
+        .await?;
+    let cached: Vec<CacheRow> = response.take(0)?;
+
+    if let Some(row) = cached.into_iter().next() {
+        CACHE_HITS.fetch_add(1, Ordering::Relaxed);
+        return Ok(row.vector);
+    }
+
+    CACHE_MISSES.fetch_add(1, Ordering::Relaxed);
+    let vector = embed_text(normalized).await?;
+
+    // The SELECT above and this CREATE aren't atomic, so a concurrent call
+    // embedding the same new text can race us here: `cache_hash_idx` is
+    // UNIQUE on `hash`, so the loser's CREATE errors instead of silently
+    // duplicating a row. Treat that as a cache hit - the winner's row has
+    // the same vector we'd have written - rather than failing the call.
+    let create_result = db.query("CREATE cache SET hash = $hash, vector = $vector")
+        .bind(("hash", hash.clone()))
+        .bind(("vector", vector.clone()))
+        .await;
+
+    if create_result.is_err() {
+        let mut response = db.query("SELECT vector FROM cache WHERE hash = $hash LIMIT 1")
+            .bind(("hash", hash))
+            .await?;
+        let winner: Vec<CacheRow> = response.take(0)?;
+        if let Some(row) = winner.into_iter().next() {
+            return Ok(row.vector);
+        }
+        create_result?;
+    }
+
+    Ok(vector)
+}
+
+// Rough whitespace-based token estimate, good enough for batch-flush
+// decisions without pulling in the model's own tokenizer for that alone.
+fn estimate_tokens(text: &str) -> usize {
+    text.split_whitespace().count().max(1)
+}
+
+// One item submitted to embed_batch: the text to embed plus whatever
+// metadata accompanied it, same shape embed() accepts.
+struct BatchItem {
+    text: String,
+    metadata: Option<serde_json::Value>,
+}
+
+// embed()/embed_batch accept either plain text (the original contract) or a
+// JSON payload `{text, file_path, symbol_name, signature, line, summary, ...}`.
+// Any fields beyond `text` are persisted verbatim as the `metadata` object,
+// so callers can render results without a second lookup.
+fn parse_embed_payload(input: &str) -> (String, Option<serde_json::Value>) {
+    if let Ok(serde_json::Value::Object(mut obj)) = serde_json::from_str::<serde_json::Value>(input) {
+        if let Some(serde_json::Value::String(text)) = obj.remove("text") {
+            let metadata = if obj.is_empty() { None } else { Some(serde_json::Value::Object(obj)) };
+            return (text, metadata);
+        }
+    }
+    (input.to_string(), None)
+}
+
+// Same contract as parse_embed_payload, but for a value already parsed out
+// of embed_batch's input array (each element is itself a JSON string or a
+// `{text, ...}` object, not a JSON-encoded string).
+fn embed_payload_from_value(value: serde_json::Value) -> BatchItem {
+    match value {
+        serde_json::Value::String(text) => BatchItem { text, metadata: None },
+        serde_json::Value::Object(mut obj) => match obj.remove("text") {
+            Some(serde_json::Value::String(text)) => {
+                let metadata = if obj.is_empty() { None } else { Some(serde_json::Value::Object(obj)) };
+                BatchItem { text, metadata }
+            }
+            _ => BatchItem { text: serde_json::Value::Object(obj).to_string(), metadata: None },
+        },
+        other => BatchItem { text: other.to_string(), metadata: None },
+    }
+}
+
+// Splits submitted items into flush-sized chunks: a chunk closes once its
+// accumulated token estimate would exceed `token_budget` or it reaches
+// `max_items`, whichever comes first. Each chunk becomes one embed_query
+// call plus one atomic multi-row INSERT in flush_batch.
+fn chunk_for_batch<'a>(items: &'a [BatchItem], token_budget: usize, max_items: usize) -> Vec<Vec<&'a BatchItem>> {
+    let mut chunks: Vec<Vec<&BatchItem>> = Vec::new();
+    let mut current: Vec<&BatchItem> = Vec::new();
+    let mut current_tokens = 0usize;
+
+    for item in items {
+        let tokens = estimate_tokens(&item.text);
+        if !current.is_empty() && (current_tokens + tokens > token_budget || current.len() >= max_items) {
+            chunks.push(std::mem::take(&mut current));
+            current_tokens = 0;
+        }
+        current_tokens += tokens;
+        current.push(item);
+    }
+
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+
+    chunks
+}
+
+#[cfg(test)]
+mod chunk_for_batch_tests {
+    use super::*;
+
+    fn item(text: &str) -> BatchItem {
+        BatchItem { text: text.to_string(), metadata: None }
+    }
+
+    #[test]
+    fn chunk_closes_exactly_at_token_budget() {
+        // Each item is 2 tokens; a budget of 4 fits exactly two items.
+        let items = vec![item("a a"), item("b b"), item("c c")];
+
+        let chunks = chunk_for_batch(&items, 4, 32);
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].len(), 2);
+        assert_eq!(chunks[1].len(), 1);
+    }
+
+    #[test]
+    fn single_oversized_item_gets_its_own_chunk_instead_of_being_dropped() {
+        let items = vec![item("a"), item("this item alone exceeds the budget"), item("b")];
+
+        let chunks = chunk_for_batch(&items, 2, 32);
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[1].len(), 1);
+        assert_eq!(chunks[1][0].text, "this item alone exceeds the budget");
+    }
+
+    #[test]
+    fn max_items_closes_a_chunk_before_the_token_budget_is_hit() {
+        let items = vec![item("a"), item("b"), item("c")];
+
+        let chunks = chunk_for_batch(&items, 1_000, 2);
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].len(), 2);
+        assert_eq!(chunks[1].len(), 1);
+    }
+
+    #[test]
+    fn empty_input_produces_no_chunks() {
+        let items: Vec<BatchItem> = Vec::new();
+
+        let chunks = chunk_for_batch(&items, 100, 32);
+
+        assert!(chunks.is_empty());
+    }
+}
+
+// Embeds and atomically persists one flush-sized chunk: a single
+// embed_query call over the whole slice, then a single multi-row INSERT
+// so a crash can never leave half a chunk indexed. Returns Ids in the
+// same order as `chunk`.
+async fn flush_batch(chunk: &[&BatchItem]) -> Result<Vec<String>> {
+    let embedder = unsafe {
+        EMBEDDER.as_ref().ok_or_else(|| anyhow::anyhow!("Embedder not initialized"))?
+    };
+    let db = unsafe {
+        DATABASE.as_ref().ok_or_else(|| anyhow::anyhow!("Database not initialized"))?
+    };
+
+    let texts: Vec<&str> = chunk.iter().map(|item| item.text.as_str()).collect();
+    let embed_data = embed_query(&texts, embedder, None).await?;
+    if embed_data.len() != chunk.len() {
+        return Err(anyhow::anyhow!(
+            "embed_query returned {} vectors for {} inputs",
+            embed_data.len(),
+            chunk.len()
+        ));
     }
+
+    #[derive(Serialize)]
+    struct BatchRow {
+        text: String,
+        vector: Vec<f32>,
+        vkey: i64,
+        metadata: Option<serde_json::Value>,
+    }
+
+    let mut rows = Vec::with_capacity(chunk.len());
+    let mut vkeys = Vec::with_capacity(chunk.len());
+    for (item, data) in chunk.iter().zip(embed_data.iter()) {
+        let vkey = VKEY_COUNTER.fetch_add(1, Ordering::SeqCst);
+        let vector = data.embedding.to_dense()?;
+        vkeys.push((vkey, vector.clone()));
+        rows.push(BatchRow { text: item.text.clone(), vector, vkey: vkey as i64, metadata: item.metadata.clone() });
+    }
+
+    #[derive(Serialize, Deserialize, Debug)]
+    struct InsertedRecord {
+        id: Thing,
+    }
+
+    let mut response = db.query("INSERT INTO embeddings $rows")
+        .bind(("rows", rows))
+        .await?;
+    let inserted: Vec<InsertedRecord> = response.take(0)?;
+
+    if let Some(index) = USEARCH_INDEX.get() {
+        for (vkey, vector) in &vkeys {
+            index.add(*vkey, vector)?;
+        }
+    }
+
+    Ok(inserted.into_iter().map(|r| r.id.id.to_string()).collect())
 }
 
 // Helper: Convert SurrealDB Id to C string for FFI
@@ -196,14 +950,28 @@ pub extern "C" fn with_config(config_json: *const c_char) -> i32 {
         Ok(e) => Arc::new(e),
         Err(_) => return -4,
     };
-    
+
+    // Build the in-memory ANN index (empty until embed()/rebuild_index() populate it)
+    let index = match build_usearch_index(&config) {
+        Ok(idx) => idx,
+        Err(_) => return -5,
+    };
+    let _ = USEARCH_INDEX.set(index);
+
+    // Load the model's own tokenizer, used to truncate/window oversized inputs
+    let tokenizer = match Tokenizer::from_pretrained(&config.embedding.model_id, None) {
+        Ok(t) => t,
+        Err(_) => return -6,
+    };
+    let _ = TOKENIZER.set(tokenizer);
+
     // Store global state
     unsafe {
         DATABASE = Some(db);
         EMBEDDER = Some(embedder);
         CONFIG = Some(config);
     }
-    
+
     0 // Success
 }
 
@@ -222,30 +990,41 @@ pub extern "C" fn embed(text: *const c_char) -> *const c_char {
         }
     };
     
+    let (embed_text_str, metadata) = parse_embed_payload(text_str);
+
     let result = runtime.block_on(async {
         // Generate embedding vector
-        let vector = embed_text(text_str).await?;
-        
+        let vector = embed_cached(&embed_text_str).await?;
+        let vkey = VKEY_COUNTER.fetch_add(1, Ordering::SeqCst);
+
         // Store in database with auto-generated ID
         let db = unsafe { DATABASE.as_ref().unwrap() };
-        let mut response = db.query("CREATE embeddings SET vector = $vector")
-            .bind(("vector", vector))
+        let mut response = db.query("CREATE embeddings SET vector = $vector, text = $text, vkey = $vkey, metadata = $metadata")
+            .bind(("vector", vector.clone()))
+            .bind(("text", embed_text_str.clone()))
+            .bind(("vkey", vkey as i64))
+            .bind(("metadata", metadata))
             .await?;
-        
+
         // Use proper SurrealDB record type with Thing ID
         #[derive(Serialize, Deserialize, Debug)]
         struct EmbeddingRecord {
             id: Thing,
             vector: Vec<f32>,
         }
-        
+
         let records: Vec<EmbeddingRecord> = response.take(0)?;
-        
+
         if let Some(record) = records.first() {
             let id: Id = Id::from(record.id.id.clone());
+
+            if let Some(index) = USEARCH_INDEX.get() {
+                index.add(vkey, &vector)?;
+            }
+
             return Ok(id);
         }
-        
+
         Err(anyhow::anyhow!("Failed to extract created ID"))
     });
     
@@ -255,6 +1034,64 @@ pub extern "C" fn embed(text: *const c_char) -> *const c_char {
     }
 }
 
+// Batched embedding: accumulate the submitted texts into flush-sized chunks
+// (by token budget or item count, whichever trips first) and embed each
+// chunk with a single embed_query + single atomic INSERT, instead of
+// embed()'s one-text-at-a-time round trip. Returns a JSON array of Ids in
+// submission order.
+#[no_mangle]
+pub extern "C" fn embed_batch(texts_json: *const c_char) -> *const c_char {
+    let runtime = match RUNTIME.get() {
+        Some(rt) => rt,
+        None => return ptr::null(),
+    };
+
+    let texts_str = unsafe {
+        match CStr::from_ptr(texts_json).to_str() {
+            Ok(s) => s,
+            Err(_) => return ptr::null(),
+        }
+    };
+
+    let values: Vec<serde_json::Value> = match serde_json::from_str(texts_str) {
+        Ok(t) => t,
+        Err(_) => return ptr::null(),
+    };
+    let items: Vec<BatchItem> = values.into_iter().map(embed_payload_from_value).collect();
+
+    let config = match unsafe { CONFIG.as_ref() } {
+        Some(c) => c,
+        None => return ptr::null(),
+    };
+    let token_budget = config.embedding.token_budget.unwrap_or(8_192);
+    let max_items = config.embedding.batch_size.unwrap_or(32);
+
+    let chunks = chunk_for_batch(&items, token_budget, max_items);
+
+    let result = runtime.block_on(async {
+        let mut ids: Vec<String> = Vec::with_capacity(items.len());
+        for chunk in chunks {
+            ids.extend(flush_batch(&chunk).await?);
+        }
+        Ok::<Vec<String>, anyhow::Error>(ids)
+    });
+
+    match result {
+        Ok(ids) => match serde_json::to_string(&ids) {
+            Ok(json) => match CString::new(json) {
+                Ok(cstring) => {
+                    let ptr = cstring.as_ptr();
+                    std::mem::forget(cstring);
+                    ptr
+                }
+                Err(_) => ptr::null(),
+            },
+            Err(_) => ptr::null(),
+        },
+        Err(_) => ptr::null(),
+    }
+}
+
 // PERFECT API Function 2: query(text) -> *const c_char (JSON SearchMatch[])
 #[no_mangle]
 pub extern "C" fn query(text: *const c_char) -> *const c_char {
@@ -271,44 +1108,376 @@ pub extern "C" fn query(text: *const c_char) -> *const c_char {
     };
     
     let result = runtime.block_on(async {
-        // Generate query vector
-        let query_vector = embed_text(text_str).await?;
-        
-        // Get configuration
         let config = unsafe { CONFIG.as_ref().unwrap() };
-        
-        // Build and execute PERFECT query
         let db = unsafe { DATABASE.as_ref().unwrap() };
-        let query_template = get_query_template(&config.ranking);
-        
-        let mut response = db.query(query_template)
-            .bind(("query_vector", query_vector))
-            .bind(("threshold", config.threshold))
-            .bind(("limit", config.limit as i64))
-            .await?;
-        
-        // Use proper SurrealDB record type for query results
+        run_query(db, config, text_str).await
+    });
+
+    match result {
+        Ok(matches) => matches_to_cstring(&matches),
+        Err(_) => ptr::null(),
+    }
+}
+
+// Shared query pipeline: embeds `text`, picks hybrid/ANN/brute-force per
+// `config`, and returns the resulting matches. Used by both the `query()`
+// FFI entry (against the global CONFIG) and evaluate() (against per-run
+// threshold/limit overrides) so they can never drift apart.
+async fn run_query(db: &Surreal<Db>, config: &GlobalConfig, text: &str) -> Result<Vec<SearchMatch>> {
+    let query_vector = embed_cached(text).await?;
+
+    if let Some(hybrid) = &config.hybrid {
+        return query_hybrid(db, config, hybrid, query_vector, text).await;
+    }
+
+    if USEARCH_INDEX.get().filter(|idx| idx.size() > 0 && ann_supported(&config.ranking)).is_some() {
+        let scored = query_vector_ann(db, config, &query_vector).await?;
+        return to_search_matches(db, config, scored).await;
+    }
+
+    // Build and execute PERFECT query
+    let query_template = get_query_template(&config.ranking);
+
+    let mut response = db.query(query_template)
+        .bind(("query_vector", query_vector))
+        .bind(("threshold", config.threshold))
+        .bind(("limit", config.limit as i64))
+        .await?;
+
+    // Use proper SurrealDB record type for query results
+    #[derive(Serialize, Deserialize, Debug)]
+    struct QueryResult {
+        id: Thing,
+        similarity_score: f64,
+    }
+
+    let results: Vec<QueryResult> = response.take(0)?;
+    let scored: Vec<(Id, f32)> = results
+        .into_iter()
+        .map(|result| (result.id.id, result.similarity_score as f32))
+        .collect();
+
+    to_search_matches(db, config, scored).await
+}
+
+// Repopulate the in-memory HNSW index from SurrealDB on cold start, since
+// usearch indexes live only in memory and don't survive a process restart.
+#[no_mangle]
+pub extern "C" fn rebuild_index() -> i32 {
+    let runtime = match RUNTIME.get() {
+        Some(rt) => rt,
+        None => return -1,
+    };
+    let index = match USEARCH_INDEX.get() {
+        Some(idx) => idx,
+        None => return -2,
+    };
+
+    let result = runtime.block_on(async {
         #[derive(Serialize, Deserialize, Debug)]
-        struct QueryResult {
-            id: Thing,
-            similarity_score: f64,
+        struct IndexedRow {
+            vkey: i64,
+            vector: Vec<f32>,
         }
-        
-        let results: Vec<QueryResult> = response.take(0)?;
-        
-        let matches: Vec<SearchMatch> = results
-            .into_iter()
-            .map(|result| SearchMatch {
-                id: result.id.id.to_string(),
-                similarity_score: result.similarity_score as f32,
-            })
-            .collect();
-        
-        Ok::<Vec<SearchMatch>, anyhow::Error>(matches)
+
+        let db = unsafe { DATABASE.as_ref().unwrap() };
+        let mut response = db.query("SELECT vkey, vector FROM embeddings").await?;
+        let rows: Vec<IndexedRow> = response.take(0)?;
+
+        index.reset()?;
+        let mut max_vkey: u64 = 0;
+        for row in rows {
+            let vkey = row.vkey as u64;
+            index.add(vkey, &row.vector)?;
+            max_vkey = max_vkey.max(vkey + 1);
+        }
+        VKEY_COUNTER.store(max_vkey, Ordering::SeqCst);
+
+        Ok::<(), anyhow::Error>(())
     });
-    
+
     match result {
-        Ok(matches) => matches_to_cstring(&matches),
+        Ok(_) => 0,
+        Err(_) => -3,
+    }
+}
+
+// Persist the HNSW index to disk via usearch's own serialization format,
+// so the next cold start can load_index() instead of paying for rebuild_index().
+#[no_mangle]
+pub extern "C" fn save_index(path: *const c_char) -> i32 {
+    let index = match USEARCH_INDEX.get() {
+        Some(idx) => idx,
+        None => return -1,
+    };
+    let path_str = unsafe {
+        match CStr::from_ptr(path).to_str() {
+            Ok(s) => s,
+            Err(_) => return -2,
+        }
+    };
+    match index.save(path_str) {
+        Ok(_) => 0,
+        Err(_) => -3,
+    }
+}
+
+// Load a previously saved HNSW index from disk, skipping rebuild_index()'s
+// full table scan. Also restores VKEY_COUNTER from the database's current
+// max vkey - without this, a freshly-started process would hand out vkeys
+// starting from 0 again, colliding with keys already present in the
+// just-loaded index on the very next embed()/embed_batch() call.
+#[no_mangle]
+pub extern "C" fn load_index(path: *const c_char) -> i32 {
+    let runtime = match RUNTIME.get() {
+        Some(rt) => rt,
+        None => return -1,
+    };
+    let index = match USEARCH_INDEX.get() {
+        Some(idx) => idx,
+        None => return -2,
+    };
+    let path_str = unsafe {
+        match CStr::from_ptr(path).to_str() {
+            Ok(s) => s,
+            Err(_) => return -3,
+        }
+    };
+    if index.load(path_str).is_err() {
+        return -4;
+    }
+
+    let result = runtime.block_on(async {
+        #[derive(Serialize, Deserialize, Debug)]
+        struct MaxVkey {
+            max: Option<i64>,
+        }
+
+        let db = unsafe { DATABASE.as_ref().ok_or_else(|| anyhow::anyhow!("Database not initialized"))? };
+        let mut response = db.query("SELECT math::max(vkey) AS max FROM embeddings GROUP ALL").await?;
+        let rows: Vec<MaxVkey> = response.take(0)?;
+        let max_vkey = rows.into_iter().next().and_then(|r| r.max).unwrap_or(-1);
+        Ok::<i64, anyhow::Error>(max_vkey)
+    });
+
+    match result {
+        Ok(max_vkey) => {
+            VKEY_COUNTER.store((max_vkey + 1) as u64, Ordering::SeqCst);
+            0
+        }
+        Err(_) => -5,
+    }
+}
+
+// Reports embed_cached's hit/miss counts as JSON, e.g. {"hits":12,"misses":3}.
+#[no_mangle]
+pub extern "C" fn cache_stats() -> *const c_char {
+    #[derive(Serialize)]
+    struct CacheStats {
+        hits: u64,
+        misses: u64,
+    }
+
+    let stats = CacheStats {
+        hits: CACHE_HITS.load(Ordering::Relaxed),
+        misses: CACHE_MISSES.load(Ordering::Relaxed),
+    };
+
+    match serde_json::to_string(&stats) {
+        Ok(json) => match CString::new(json) {
+            Ok(cstring) => {
+                let ptr = cstring.as_ptr();
+                std::mem::forget(cstring);
+                ptr
+            }
+            Err(_) => ptr::null(),
+        },
+        Err(_) => ptr::null(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct EvalCase {
+    query: String,
+    expected_matches: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EvalRequest {
+    cases: Vec<EvalCase>,
+    threshold: Option<f32>,
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct EvalCaseReport {
+    query: String,
+    precision_at_k: f32,
+    recall_at_k: f32,
+    reciprocal_rank: f32,
+}
+
+#[derive(Debug, Serialize)]
+struct EvalReport {
+    cases: Vec<EvalCaseReport>,
+    mean_precision_at_k: f32,
+    mean_recall_at_k: f32,
+    mrr: f32,
+}
+
+// Scores one eval case against the results run_query actually returns:
+// precision@k and recall@k against `expected_matches`, plus the
+// reciprocal rank of the first expected match that appears.
+fn score_case(case: &EvalCase, retrieved: &[SearchMatch]) -> EvalCaseReport {
+    let expected: std::collections::HashSet<&str> = case.expected_matches.iter().map(|s| s.as_str()).collect();
+    let retrieved_ids: Vec<&str> = retrieved.iter().map(|m| m.id.as_str()).collect();
+
+    let hits = retrieved_ids.iter().filter(|id| expected.contains(*id)).count();
+
+    let precision_at_k = if retrieved_ids.is_empty() { 0.0 } else { hits as f32 / retrieved_ids.len() as f32 };
+    let recall_at_k = if expected.is_empty() { 0.0 } else { hits as f32 / expected.len() as f32 };
+    let reciprocal_rank = retrieved_ids
+        .iter()
+        .position(|id| expected.contains(*id))
+        .map(|rank| 1.0 / (rank as f32 + 1.0))
+        .unwrap_or(0.0);
+
+    EvalCaseReport {
+        query: case.query.clone(),
+        precision_at_k,
+        recall_at_k,
+        reciprocal_rank,
+    }
+}
+
+#[cfg(test)]
+mod score_case_tests {
+    use super::*;
+
+    fn case(expected_matches: &[&str]) -> EvalCase {
+        EvalCase {
+            query: "q".to_string(),
+            expected_matches: expected_matches.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    fn matches(ids: &[&str]) -> Vec<SearchMatch> {
+        ids.iter()
+            .map(|id| SearchMatch { id: id.to_string(), similarity_score: 1.0, metadata: None })
+            .collect()
+    }
+
+    #[test]
+    fn empty_expected_matches_scores_recall_and_rank_as_zero() {
+        let case = case(&[]);
+        let retrieved = matches(&["a", "b"]);
+
+        let report = score_case(&case, &retrieved);
+
+        assert_eq!(report.recall_at_k, 0.0);
+        assert_eq!(report.precision_at_k, 0.0);
+        assert_eq!(report.reciprocal_rank, 0.0);
+    }
+
+    #[test]
+    fn empty_retrieved_scores_precision_and_rank_as_zero() {
+        let case = case(&["a"]);
+        let retrieved: Vec<SearchMatch> = Vec::new();
+
+        let report = score_case(&case, &retrieved);
+
+        assert_eq!(report.precision_at_k, 0.0);
+        assert_eq!(report.recall_at_k, 0.0);
+        assert_eq!(report.reciprocal_rank, 0.0);
+    }
+
+    #[test]
+    fn first_hit_at_rank_zero_gives_reciprocal_rank_one() {
+        let case = case(&["a"]);
+        let retrieved = matches(&["a", "b", "c"]);
+
+        let report = score_case(&case, &retrieved);
+
+        assert_eq!(report.reciprocal_rank, 1.0);
+        assert_eq!(report.precision_at_k, 1.0 / 3.0);
+        assert_eq!(report.recall_at_k, 1.0);
+    }
+
+    #[test]
+    fn first_hit_at_rank_k_gives_reciprocal_rank_one_over_k_plus_one() {
+        let case = case(&["c"]);
+        let retrieved = matches(&["a", "b", "c"]);
+
+        let report = score_case(&case, &retrieved);
+
+        assert!((report.reciprocal_rank - 1.0 / 3.0).abs() < 1e-6);
+        assert!((report.precision_at_k - 1.0 / 3.0).abs() < 1e-6);
+        assert_eq!(report.recall_at_k, 1.0);
+    }
+}
+
+// Runs a set of {query, expected_matches} cases through the same pipeline
+// as query() and reports precision@k, recall@k, and MRR, so maintainers
+// can compare RankingStrategy variants, embedding models, or the hybrid/ANN
+// changes against a fixed ground truth instead of eyeballing results.
+// `threshold`/`limit` override the configured values for this run only.
+#[no_mangle]
+pub extern "C" fn evaluate(eval_json: *const c_char) -> *const c_char {
+    let runtime = match RUNTIME.get() {
+        Some(rt) => rt,
+        None => return ptr::null(),
+    };
+
+    let eval_str = unsafe {
+        match CStr::from_ptr(eval_json).to_str() {
+            Ok(s) => s,
+            Err(_) => return ptr::null(),
+        }
+    };
+
+    let eval_request: EvalRequest = match serde_json::from_str(eval_str) {
+        Ok(r) => r,
+        Err(_) => return ptr::null(),
+    };
+
+    let result = runtime.block_on(async {
+        let base_config = unsafe { CONFIG.as_ref().ok_or_else(|| anyhow::anyhow!("Config not initialized"))? };
+        let db = unsafe { DATABASE.as_ref().ok_or_else(|| anyhow::anyhow!("Database not initialized"))? };
+
+        let mut run_config = base_config.clone();
+        if let Some(threshold) = eval_request.threshold {
+            run_config.threshold = threshold;
+        }
+        if let Some(limit) = eval_request.limit {
+            run_config.limit = limit;
+        }
+
+        let mut cases = Vec::with_capacity(eval_request.cases.len());
+        for case in &eval_request.cases {
+            let retrieved = run_query(db, &run_config, &case.query).await?;
+            cases.push(score_case(case, &retrieved));
+        }
+
+        let count = cases.len().max(1) as f32;
+        let mean_precision_at_k = cases.iter().map(|c| c.precision_at_k).sum::<f32>() / count;
+        let mean_recall_at_k = cases.iter().map(|c| c.recall_at_k).sum::<f32>() / count;
+        let mrr = cases.iter().map(|c| c.reciprocal_rank).sum::<f32>() / count;
+
+        Ok::<EvalReport, anyhow::Error>(EvalReport { cases, mean_precision_at_k, mean_recall_at_k, mrr })
+    });
+
+    match result {
+        Ok(report) => match serde_json::to_string(&report) {
+            Ok(json) => match CString::new(json) {
+                Ok(cstring) => {
+                    let ptr = cstring.as_ptr();
+                    std::mem::forget(cstring);
+                    ptr
+                }
+                Err(_) => ptr::null(),
+            },
+            Err(_) => ptr::null(),
+        },
         Err(_) => ptr::null(),
     }
 }